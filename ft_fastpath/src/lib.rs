@@ -1,9 +1,24 @@
 use anyhow::Result;
+use blake3::Hasher;
 use crossbeam_channel as chan;
+use ignore::gitignore::Gitignore;
+use ignore::overrides::OverrideBuilder;
+use ignore::types::TypesBuilder;
 use ignore::{WalkBuilder, WalkState};
+use memmap2::Mmap;
+use notify::{recommended_watcher, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[pyclass]
 #[derive(Clone)]
@@ -17,33 +32,68 @@ pub struct FileEntry {
     pub mtime_s: u64,
 }
 
-/// Scan a repository directory tree, respecting .gitignore/.ignore and git excludes.
-/// Returns a list of files (not directories), each with path, size, and mtime.
-/// `threads=0` lets the walker decide (usually #cores).
-#[pyfunction]
-pub fn scan_repo_parallel(
-    py: Python<'_>,
-    root_path: &str,
+/// Build a `WalkBuilder` configured the way every scan entry point in this
+/// crate wants it: respecting .gitignore/.ignore/git-excludes, plus the
+/// optional file-type and glob filters shared by `scan_repo_parallel` and
+/// `scan_repo_stream`. `threads=0` lets the walker decide (usually #cores).
+fn build_walker(
+    root: &Path,
     threads: usize,
-) -> PyResult<Vec<FileEntry>> {
-    let root = Path::new(root_path);
+    types: Option<&[String]>,
+    exclude_types: Option<&[String]>,
+    globs: Option<&[String]>,
+) -> PyResult<WalkBuilder> {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false) // still respects .gitignore/.ignore
+        .git_ignore(true)
+        .git_exclude(true)
+        .parents(true)
+        .follow_links(false);
 
-    let entries = py.allow_threads(|| -> Vec<FileEntry> {
-        let (tx, rx) = chan::unbounded::<FileEntry>();
+    if threads > 0 {
+        builder.threads(threads);
+    }
 
-        let mut builder = WalkBuilder::new(root);
-        builder
-            .hidden(false) // still respects .gitignore/.ignore
-            .git_ignore(true)
-            .git_exclude(true)
-            .parents(true)
-            .follow_links(false);
+    if types.is_some() || exclude_types.is_some() {
+        let mut tb = TypesBuilder::new();
+        tb.add_defaults();
+        for name in types.into_iter().flatten() {
+            tb.select(name);
+        }
+        for name in exclude_types.into_iter().flatten() {
+            tb.negate(name);
+        }
+        let matcher = tb
+            .build()
+            .map_err(|e| PyValueError::new_err(format!("invalid file type filter: {e}")))?;
+        builder.types(matcher);
+    }
 
-        if threads > 0 {
-            builder.threads(threads);
+    if let Some(globs) = globs.filter(|g| !g.is_empty()) {
+        let mut ob = OverrideBuilder::new(root);
+        for glob in globs {
+            ob.add(glob)
+                .map_err(|e| PyValueError::new_err(format!("invalid glob `{glob}`: {e}")))?;
         }
+        let overrides = ob
+            .build()
+            .map_err(|e| PyValueError::new_err(format!("invalid globs: {e}")))?;
+        builder.overrides(overrides);
+    }
 
-        // Parallel walker with a callback per entry
+    Ok(builder)
+}
+
+/// Spawn the parallel walk on a background thread and return the receiving
+/// end of the channel it feeds. Buffered callers (`scan_repo_parallel`,
+/// `diff_repo`) drain the receiver to a `Vec` before returning to Python;
+/// `scan_repo_stream` hands the receiver straight to Python instead, so
+/// entries can be processed as the walker discovers them.
+fn spawn_walk(builder: WalkBuilder) -> chan::Receiver<FileEntry> {
+    let (tx, rx) = chan::unbounded::<FileEntry>();
+
+    std::thread::spawn(move || {
         builder.build_parallel().run(|| {
             let tx = tx.clone();
             Box::new(move |entry| {
@@ -78,21 +128,848 @@ pub fn scan_repo_parallel(
                 WalkState::Continue
             })
         });
+        // `tx` (and every clone handed to worker callbacks) is dropped here,
+        // closing the channel so receivers see the walk as finished.
+    });
+
+    rx
+}
+
+/// Scan a repository directory tree, respecting .gitignore/.ignore and git excludes.
+/// Returns a list of files (not directories), each with path, size, and mtime.
+/// `threads=0` lets the walker decide (usually #cores).
+///
+/// `types`/`exclude_types` select or drop files by `ignore::types::TypesBuilder`
+/// name (e.g. `"rust"`, `"py"`), the same mechanism rustc's bootstrap formatter
+/// uses to restrict a walk to `*.rs`. `globs` are raw override patterns (e.g.
+/// `"*.py"`, `"!vendor/**"`) compiled through `ignore::overrides::OverrideBuilder`,
+/// for callers who want to filter by pattern instead of a named file type.
+#[pyfunction]
+#[pyo3(signature = (root_path, threads=0, types=None, exclude_types=None, globs=None))]
+pub fn scan_repo_parallel(
+    py: Python<'_>,
+    root_path: &str,
+    threads: usize,
+    types: Option<Vec<String>>,
+    exclude_types: Option<Vec<String>>,
+    globs: Option<Vec<String>>,
+) -> PyResult<Vec<FileEntry>> {
+    let root = Path::new(root_path);
+    let builder = build_walker(
+        root,
+        threads,
+        types.as_deref(),
+        exclude_types.as_deref(),
+        globs.as_deref(),
+    )?;
+
+    let entries = py.allow_threads(|| spawn_walk(builder).iter().collect());
+    Ok(entries)
+}
+
+#[pyclass]
+pub struct DiffResult {
+    #[pyo3(get)]
+    pub added: Vec<String>,
+    #[pyo3(get)]
+    pub modified: Vec<String>,
+    #[pyo3(get)]
+    pub removed: Vec<String>,
+    #[pyo3(get)]
+    pub clean: Vec<String>,
+}
+
+/// Diff a fresh walk of `root_path` against `prev_snapshot`, a `(path, size, mtime_s)`
+/// list from a previous `scan_repo_parallel` call: Clean if size and mtime
+/// still match, Modified if present in both but either differs, Added if new;
+/// anything left in `prev_snapshot` after the walk is Removed.
+///
+/// Only `added` and `modified` need to be re-hashed by `hash_files_parallel`.
+#[pyfunction]
+#[pyo3(signature = (root_path, prev_snapshot, threads=0))]
+pub fn diff_repo(
+    py: Python<'_>,
+    root_path: &str,
+    prev_snapshot: Vec<(String, u64, u64)>,
+    threads: usize,
+) -> PyResult<DiffResult> {
+    let root = Path::new(root_path);
+
+    let mut prev: HashMap<String, (u64, u64)> = prev_snapshot
+        .into_iter()
+        .map(|(path, size, mtime_s)| (path, (size, mtime_s)))
+        .collect();
+
+    let builder = build_walker(root, threads, None, None, None)?;
+
+    let (added, modified, clean) =
+        py.allow_threads(|| classify_snapshot(spawn_walk(builder).iter(), &mut prev));
 
-        drop(tx);
-        let mut out = Vec::new();
-        for fe in rx.iter() {
-            out.push(fe);
+    // Whatever's left in `prev` was never seen during the walk.
+    let removed: Vec<String> = prev.into_keys().collect();
+
+    Ok(DiffResult {
+        added,
+        modified,
+        removed,
+        clean,
+    })
+}
+
+/// Classify each walked `FileEntry` against `prev` (removing matches as it
+/// goes, so the caller can read whatever's left as Removed): Clean if size
+/// and mtime still match, Modified if present but either differs, Added if
+/// `prev` has no entry for the path.
+fn classify_snapshot(
+    entries: impl Iterator<Item = FileEntry>,
+    prev: &mut HashMap<String, (u64, u64)>,
+) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut added = Vec::new();
+    let mut modified = Vec::new();
+    let mut clean = Vec::new();
+    for fe in entries {
+        match prev.remove(&fe.path) {
+            Some((size, mtime_s)) if size == fe.size && mtime_s == fe.mtime_s => {
+                clean.push(fe.path)
+            }
+            Some(_) => modified.push(fe.path),
+            None => added.push(fe.path),
+        }
+    }
+    (added, modified, clean)
+}
+
+/// List files changed relative to `base_ref` (defaults to `HEAD` when empty):
+/// tracked changes from `git diff --name-only <base_ref>`, untracked-but-not-
+/// ignored files from `git ls-files --others --exclude-standard`. Each
+/// resulting path is canonicalized against `root_path` and stat'd for
+/// size/mtime; a path that cannot be stat'd (e.g. a file deleted since
+/// `base_ref`) is left out of the result.
+#[pyfunction]
+#[pyo3(signature = (root_path, base_ref=String::new(), threads=0))]
+pub fn scan_git_modified(
+    py: Python<'_>,
+    root_path: &str,
+    base_ref: String,
+    threads: usize,
+) -> PyResult<Vec<FileEntry>> {
+    let root = Path::new(root_path);
+    let base_ref = if base_ref.is_empty() {
+        "HEAD".to_string()
+    } else {
+        base_ref
+    };
+
+    let rel_paths = py
+        .allow_threads(|| git_modified_paths(root, &base_ref))
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+    let entries = py.allow_threads(|| -> Vec<FileEntry> {
+        let stat_all = |paths: Vec<String>| -> Vec<FileEntry> {
+            paths
+                .into_par_iter()
+                .filter_map(|rel| {
+                    let full = root.join(&rel);
+                    let canon = full.canonicalize().unwrap_or(full);
+                    let md = std::fs::metadata(&canon).ok()?;
+                    let mtime_s = md
+                        .modified()
+                        .ok()
+                        .and_then(|t: SystemTime| t.duration_since(UNIX_EPOCH).ok())
+                        .map(|d| d.as_secs())
+                        .unwrap_or(0);
+                    Some(FileEntry {
+                        path: canon.to_string_lossy().to_string(),
+                        size: md.len(),
+                        mtime_s,
+                    })
+                })
+                .collect()
+        };
+
+        if threads > 0 {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("failed to build rayon thread pool")
+                .install(|| stat_all(rel_paths))
+        } else {
+            stat_all(rel_paths)
         }
-        out
     });
 
     Ok(entries)
 }
 
+/// A Python iterator over `FileEntry` values pulled from a background walk:
+/// the walker threads keep feeding a `crossbeam_channel`, and `__next__` just
+/// blocks on the next message instead of waiting for the whole tree to be
+/// collected.
+#[pyclass]
+pub struct ScanStream {
+    rx: chan::Receiver<FileEntry>,
+}
+
+#[pymethods]
+impl ScanStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(slf: PyRef<'_, Self>, py: Python<'_>) -> Option<FileEntry> {
+        let rx = slf.rx.clone();
+        py.allow_threads(|| rx.recv().ok())
+    }
+}
+
+/// Same filtering as `scan_repo_parallel`, but returns a `ScanStream` instead
+/// of a `Vec<FileEntry>`: the walk runs in the background and entries are
+/// yielded to Python one at a time as the walker finds them.
+#[pyfunction]
+#[pyo3(signature = (root_path, threads=0, types=None, exclude_types=None, globs=None))]
+pub fn scan_repo_stream(
+    root_path: &str,
+    threads: usize,
+    types: Option<Vec<String>>,
+    exclude_types: Option<Vec<String>>,
+    globs: Option<Vec<String>>,
+) -> PyResult<ScanStream> {
+    let root = Path::new(root_path);
+    let builder = build_walker(
+        root,
+        threads,
+        types.as_deref(),
+        exclude_types.as_deref(),
+        globs.as_deref(),
+    )?;
+
+    Ok(ScanStream {
+        rx: spawn_walk(builder),
+    })
+}
+
+/// Run the two git subcommands and merge their output into a sorted, deduped
+/// set of paths relative to `root` (not necessarily the repo's top-level
+/// directory). `git diff --name-only` reports paths relative to the repo
+/// root regardless of `-C`, so it needs `--relative` to match `root`;
+/// `git ls-files --others` is already `-C`-relative by default.
+fn git_modified_paths(root: &Path, base_ref: &str) -> Result<Vec<String>> {
+    let mut paths = BTreeSet::new();
+
+    let diff_out = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["diff", "--relative", "--name-only", base_ref])
+        .output()?;
+    if !diff_out.status.success() {
+        anyhow::bail!(
+            "git diff --name-only {base_ref} failed: {}",
+            String::from_utf8_lossy(&diff_out.stderr)
+        );
+    }
+    paths.extend(
+        String::from_utf8_lossy(&diff_out.stdout)
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(str::to_string),
+    );
+
+    let untracked_out = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["ls-files", "--others", "--exclude-standard"])
+        .output()?;
+    if !untracked_out.status.success() {
+        anyhow::bail!(
+            "git ls-files --others --exclude-standard failed: {}",
+            String::from_utf8_lossy(&untracked_out.stderr)
+        );
+    }
+    paths.extend(
+        String::from_utf8_lossy(&untracked_out.stdout)
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(str::to_string),
+    );
+
+    Ok(paths.into_iter().collect())
+}
+
+/// One filesystem change: `kind` is one of `"created"`, `"modified"`,
+/// `"removed"`, and `size`/`mtime_s` are stat'd when the latest raw event for
+/// the path arrives (0 for a path that's already gone by then, e.g. a fast
+/// create-then-delete).
+#[pyclass]
+#[derive(Clone)]
+pub struct WatchEvent {
+    #[pyo3(get)]
+    pub path: String,
+    #[pyo3(get)]
+    pub kind: String,
+    #[pyo3(get)]
+    pub size: u64,
+    #[pyo3(get)]
+    pub mtime_s: u64,
+}
+
+fn classify_event_kind(kind: &EventKind) -> &'static str {
+    match kind {
+        EventKind::Create(_) => "created",
+        EventKind::Remove(_) => "removed",
+        _ => "modified",
+    }
+}
+
+/// Check `path` against the same nested-`.gitignore`/`.ignore`/git-exclude
+/// rules `build_walker`'s `WalkBuilder` honors: walk from `path`'s directory
+/// up to `root`, checking each level's own `.gitignore`/`.ignore` first (the
+/// closest directory wins, same precedence git gives nested ignore files),
+/// then fall back to `root/.git/info/exclude`. A path inside an ignored
+/// directory counts as ignored, as it does for the walker.
+fn path_is_ignored(root: &Path, path: &Path, is_dir: bool) -> bool {
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        for name in [".gitignore", ".ignore"] {
+            let candidate = d.join(name);
+            if candidate.is_file() {
+                let (gi, _) = Gitignore::new(&candidate);
+                match gi.matched_path_or_any_parents(path, is_dir) {
+                    ignore::Match::Ignore(_) => return true,
+                    ignore::Match::Whitelist(_) => return false,
+                    ignore::Match::None => {}
+                }
+            }
+        }
+        if d == root || !d.starts_with(root) {
+            break;
+        }
+        dir = d.parent();
+    }
+
+    let exclude = root.join(".git").join("info").join("exclude");
+    if exclude.is_file() {
+        if let Ok(rel) = path.strip_prefix(root) {
+            let (gi, _) = Gitignore::new(&exclude);
+            if gi.matched_path_or_any_parents(rel, is_dir).is_ignore() {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+fn stat_path(path: &Path) -> (u64, u64) {
+    match std::fs::metadata(path) {
+        Ok(md) => {
+            let mtime_s = md
+                .modified()
+                .ok()
+                .and_then(|t: SystemTime| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            (md.len(), mtime_s)
+        }
+        Err(_) => (0, 0),
+    }
+}
+
+/// A long-lived, debounced filesystem watch over `root_path`. Events are a
+/// Python iterator, same shape as `ScanStream`: `__next__` blocks on the next
+/// coalesced batch member.
+///
+/// `pause()`/`resume()` buffer events while paused and flush them in order on
+/// resume.
+#[pyclass]
+pub struct RepoWatcher {
+    rx: chan::Receiver<WatchEvent>,
+    tx: chan::Sender<WatchEvent>,
+    paused: Arc<AtomicBool>,
+    buffer: Arc<Mutex<Vec<WatchEvent>>>,
+    // Kept alive for the lifetime of the watcher; dropping it stops the watch.
+    _watcher: RecommendedWatcher,
+}
+
+#[pymethods]
+impl RepoWatcher {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(slf: PyRef<'_, Self>, py: Python<'_>) -> Option<WatchEvent> {
+        let rx = slf.rx.clone();
+        py.allow_threads(|| rx.recv().ok())
+    }
+
+    /// Stop delivering events to Python; the watcher keeps running and
+    /// buffers whatever it sees until `resume()` is called.
+    fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resume delivery, flushing anything buffered while paused, oldest first.
+    fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+        let mut buffered = self.buffer.lock().unwrap();
+        for event in buffered.drain(..) {
+            let _ = self.tx.send(event);
+        }
+    }
+}
+
+/// Watch a repository for changes, coalescing bursts with a short debounce
+/// window and filtering every changed path through the same nested
+/// `.gitignore`/`.ignore`/git-exclude rules `scan_repo_parallel`'s
+/// `WalkBuilder` respects (see `path_is_ignored`), so editor swap files,
+/// build output, etc. never show up as events.
+#[pyfunction]
+pub fn watch_repo(root_path: &str) -> PyResult<RepoWatcher> {
+    let root = PathBuf::from(root_path);
+
+    let (raw_tx, raw_rx) = std::sync::mpsc::channel();
+    let mut watcher = recommended_watcher(raw_tx)
+        .map_err(|e| PyRuntimeError::new_err(format!("failed to start watcher: {e}")))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| PyRuntimeError::new_err(format!("failed to watch {root_path}: {e}")))?;
+
+    let (tx, rx) = chan::unbounded::<WatchEvent>();
+    let paused = Arc::new(AtomicBool::new(false));
+    let buffer: Arc<Mutex<Vec<WatchEvent>>> = Arc::new(Mutex::new(Vec::new()));
+
+    {
+        let tx = tx.clone();
+        let paused = paused.clone();
+        let buffer = buffer.clone();
+        let root = root.clone();
+        std::thread::spawn(move || {
+            const DEBOUNCE: Duration = Duration::from_millis(200);
+            let mut pending: HashMap<PathBuf, WatchEvent> = HashMap::new();
+
+            loop {
+                match raw_rx.recv_timeout(DEBOUNCE) {
+                    Ok(Ok(event)) => {
+                        for path in event.paths {
+                            let is_dir = path.is_dir();
+                            if path_is_ignored(&root, &path, is_dir) {
+                                continue;
+                            }
+                            let (size, mtime_s) = stat_path(&path);
+                            pending.insert(
+                                path.clone(),
+                                WatchEvent {
+                                    path: path.to_string_lossy().to_string(),
+                                    kind: classify_event_kind(&event.kind).to_string(),
+                                    size,
+                                    mtime_s,
+                                },
+                            );
+                        }
+                    }
+                    Ok(Err(_)) => {}
+                    Err(RecvTimeoutError::Timeout) => {
+                        if pending.is_empty() {
+                            continue;
+                        }
+                        let batch: Vec<WatchEvent> = pending.drain().map(|(_, e)| e).collect();
+                        if paused.load(Ordering::SeqCst) {
+                            buffer.lock().unwrap().extend(batch);
+                        } else {
+                            for event in batch {
+                                let _ = tx.send(event);
+                            }
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+    }
+
+    Ok(RepoWatcher {
+        rx,
+        tx,
+        paused,
+        buffer,
+        _watcher: watcher,
+    })
+}
+
+/// Hash one file: memory-map it and run blake3's rayon-parallel update over
+/// the mapping so large files are hashed across cores instead of on a single
+/// thread. Returns `Ok(None)` for a file over `max_filesize` (skipped, not an
+/// error). Empty files are never mmap'd (zero-length mappings fail on some
+/// platforms) and just get blake3's well-known empty-input digest.
+fn hash_one_file(path: &str, max_filesize: Option<u64>) -> Result<Option<String>> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    if let Some(max) = max_filesize {
+        if len > max {
+            return Ok(None);
+        }
+    }
+
+    if len == 0 {
+        return Ok(Some(Hasher::new().finalize().to_hex().to_string()));
+    }
+
+    let mmap = unsafe { Mmap::map(&file)? };
+    let mut hasher = Hasher::new();
+    hasher.update_rayon(&mmap);
+    Ok(Some(hasher.finalize().to_hex().to_string()))
+}
+
+/// Hash every path in parallel via `hash_one_file`, keeping the raw
+/// `Result` per path instead of formatting it: `hash_files_parallel` turns
+/// errors into display strings for Python, `hash_tree` needs to tell a real
+/// digest apart from a failure so it doesn't fold error text into the root.
+fn hash_files_parallel_results(
+    paths: &[String],
+    max_filesize: Option<u64>,
+) -> Vec<(String, Result<Option<String>>)> {
+    paths
+        .par_iter()
+        .map(|p| (p.clone(), hash_one_file(p, max_filesize)))
+        .collect()
+}
+
+/// Hash a batch of files in parallel. Each file is memory-mapped and hashed
+/// with blake3's `update_rayon` (rayon feature) instead of `fs::read` +
+/// single-threaded `Hasher::update`, so large files spread across cores
+/// instead of blocking one. `max_filesize` skips (omits from the result)
+/// anything larger; an unreadable path surfaces as an `"error: ..."` entry
+/// rather than silently hashing empty data.
+#[pyfunction]
+#[pyo3(signature = (paths, max_filesize=None))]
+pub fn hash_files_parallel(
+    paths: Vec<String>,
+    max_filesize: Option<u64>,
+) -> PyResult<Vec<(String, String)>> {
+    let out = hash_files_parallel_results(&paths, max_filesize)
+        .into_iter()
+        .filter_map(|(p, result)| match result {
+            Ok(Some(hash)) => Some((p, hash)),
+            Ok(None) => None,
+            Err(e) => Some((p, format!("error: {e}"))),
+        })
+        .collect();
+    Ok(out)
+}
+
+/// Hash every file in `paths`, then fold the per-file `(path, hash)` pairs —
+/// sorted by path for determinism — into a single repository Merkle root by
+/// feeding `path\0hash\n` for each into a top-level `Hasher`. Returns the root
+/// as hex alongside the per-file pairs that produced it.
+///
+/// A path that fails to hash still appears in the returned pairs as an
+/// `"error: ..."` entry (same as `hash_files_parallel`), but is excluded from
+/// the root: OS error text isn't stable across platforms/locales, so folding
+/// it in would make the "same" failure produce a different root depending on
+/// where it ran.
+#[pyfunction]
+#[pyo3(signature = (paths, max_filesize=None))]
+pub fn hash_tree(
+    paths: Vec<String>,
+    max_filesize: Option<u64>,
+) -> PyResult<(String, Vec<(String, String)>)> {
+    let mut results = hash_files_parallel_results(&paths, max_filesize);
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut root_hasher = Hasher::new();
+    let mut pairs = Vec::with_capacity(results.len());
+    for (path, result) in results {
+        match result {
+            Ok(Some(hash)) => {
+                root_hasher.update(path.as_bytes());
+                root_hasher.update(b"\0");
+                root_hasher.update(hash.as_bytes());
+                root_hasher.update(b"\n");
+                pairs.push((path, hash));
+            }
+            Ok(None) => {}
+            Err(e) => pairs.push((path, format!("error: {e}"))),
+        }
+    }
+
+    Ok((root_hasher.finalize().to_hex().to_string(), pairs))
+}
+
 #[pymodule]
 fn ft_fastpath(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<FileEntry>()?;
+    m.add_class::<DiffResult>()?;
+    m.add_class::<ScanStream>()?;
+    m.add_class::<WatchEvent>()?;
+    m.add_class::<RepoWatcher>()?;
     m.add_function(wrap_pyfunction!(scan_repo_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(diff_repo, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_git_modified, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_repo_stream, m)?)?;
+    m.add_function(wrap_pyfunction!(watch_repo, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_files_parallel, m)?)?;
+    m.add_function(wrap_pyfunction!(hash_tree, m)?)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, size: u64, mtime_s: u64) -> FileEntry {
+        FileEntry {
+            path: path.to_string(),
+            size,
+            mtime_s,
+        }
+    }
+
+    fn walked_file_names(builder: WalkBuilder) -> Vec<String> {
+        let mut names: Vec<String> = builder
+            .build()
+            .filter_map(|r| r.ok())
+            .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn build_walker_types_filters_to_selected_file_type() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_types_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("b.py"), "pass").unwrap();
+        std::fs::write(dir.join("c.txt"), "hello").unwrap();
+
+        let builder = build_walker(&dir, 0, Some(&["rust".to_string()]), None, None).unwrap();
+        assert_eq!(walked_file_names(builder), vec!["a.rs".to_string()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_walker_exclude_types_drops_the_named_type() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_exclude_types_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.join("b.py"), "pass").unwrap();
+
+        let builder = build_walker(&dir, 0, None, Some(&["py".to_string()]), None).unwrap();
+        assert_eq!(walked_file_names(builder), vec!["a.rs".to_string()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_walker_globs_override_excludes_matching_paths() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_globs_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("vendor")).unwrap();
+        std::fs::write(dir.join("foo.txt"), "keep").unwrap();
+        std::fs::write(dir.join("vendor").join("bar.txt"), "drop").unwrap();
+
+        let builder = build_walker(&dir, 0, None, None, Some(&["!vendor/**".to_string()])).unwrap();
+        assert_eq!(walked_file_names(builder), vec!["foo.txt".to_string()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn classify_snapshot_buckets_by_size_and_mtime() {
+        let mut prev: HashMap<String, (u64, u64)> = [
+            ("clean.txt".to_string(), (10, 100)),
+            ("modified.txt".to_string(), (10, 100)),
+            ("removed.txt".to_string(), (10, 100)),
+        ]
+        .into_iter()
+        .collect();
+
+        let walked = vec![
+            entry("clean.txt", 10, 100),
+            entry("modified.txt", 11, 100),
+            entry("added.txt", 5, 50),
+        ];
+
+        let (added, modified, clean) = classify_snapshot(walked.into_iter(), &mut prev);
+
+        assert_eq!(added, vec!["added.txt"]);
+        assert_eq!(modified, vec!["modified.txt"]);
+        assert_eq!(clean, vec!["clean.txt"]);
+        // Only the untouched entry is left for the caller to report as Removed.
+        assert_eq!(prev.into_keys().collect::<Vec<_>>(), vec!["removed.txt"]);
+    }
+
+    #[test]
+    fn hash_one_file_empty_file_matches_blake3_empty_digest() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_empty_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("empty.txt");
+        std::fs::write(&path, b"").unwrap();
+
+        let hash = hash_one_file(path.to_str().unwrap(), None).unwrap();
+        assert_eq!(hash, Some(Hasher::new().finalize().to_hex().to_string()));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hash_one_file_skips_when_over_max_filesize() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_maxsize_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("big.txt");
+        std::fs::write(&path, vec![b'x'; 100]).unwrap();
+
+        assert_eq!(hash_one_file(path.to_str().unwrap(), Some(10)).unwrap(), None);
+        assert!(hash_one_file(path.to_str().unwrap(), Some(1000)).unwrap().is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hash_one_file_missing_path_is_an_error() {
+        assert!(hash_one_file("/no/such/path/for/ft-fastpath-tests", None).is_err());
+    }
+
+    #[test]
+    fn hash_tree_is_deterministic_regardless_of_input_order() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_tree_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.txt");
+        let b = dir.join("b.txt");
+        std::fs::write(&a, b"hello").unwrap();
+        std::fs::write(&b, b"world").unwrap();
+
+        let forward = hash_tree(
+            vec![a.to_str().unwrap().to_string(), b.to_str().unwrap().to_string()],
+            None,
+        )
+        .unwrap();
+        let reversed = hash_tree(
+            vec![b.to_str().unwrap().to_string(), a.to_str().unwrap().to_string()],
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(forward.0, reversed.0);
+        assert_eq!(forward.1[0].0, a.to_str().unwrap());
+        assert_eq!(forward.1[1].0, b.to_str().unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn hash_tree_root_ignores_unreadable_paths() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_tree_err_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.txt");
+        std::fs::write(&a, b"hello").unwrap();
+        let missing = dir.join("missing.txt").to_str().unwrap().to_string();
+
+        let with_error = hash_tree(vec![a.to_str().unwrap().to_string(), missing.clone()], None).unwrap();
+        let without_error = hash_tree(vec![a.to_str().unwrap().to_string()], None).unwrap();
+
+        // The failing path still shows up for inspection, but doesn't move the root.
+        assert_eq!(with_error.0, without_error.0);
+        assert_eq!(with_error.1.len(), 2);
+        assert!(with_error.1.iter().any(|(p, h)| p == &missing && h.starts_with("error: ")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn git_modified_paths_are_relative_to_a_subdirectory_root() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_git_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let sub = dir.join("sub");
+        std::fs::create_dir_all(&sub).unwrap();
+
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .arg("-C")
+                .arg(&dir)
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&["init", "-q"]);
+        git(&["config", "user.email", "test@test.com"]);
+        git(&["config", "user.name", "test"]);
+        std::fs::write(sub.join("a.txt"), "one\n").unwrap();
+        git(&["add", "-A"]);
+        git(&["commit", "-q", "-m", "init"]);
+        std::fs::write(sub.join("a.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(sub.join("untracked.txt"), "new\n").unwrap();
+
+        let mut paths = git_modified_paths(&sub, "HEAD").unwrap();
+        paths.sort();
+        assert_eq!(paths, vec!["a.txt".to_string(), "untracked.txt".to_string()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn spawn_walk_yields_every_file_in_the_tree() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_stream_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "one").unwrap();
+        std::fs::write(dir.join("b.txt"), "two").unwrap();
+
+        let builder = build_walker(&dir, 0, None, None, None).unwrap();
+        let rx = spawn_walk(builder);
+        let mut names: Vec<String> = rx
+            .iter()
+            .map(|fe| Path::new(&fe.path).file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+
+        assert_eq!(names, vec!["a.txt".to_string(), "b.txt".to_string()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn classify_event_kind_maps_notify_kinds() {
+        assert_eq!(classify_event_kind(&EventKind::Create(notify::event::CreateKind::File)), "created");
+        assert_eq!(classify_event_kind(&EventKind::Remove(notify::event::RemoveKind::File)), "removed");
+        assert_eq!(classify_event_kind(&EventKind::Modify(notify::event::ModifyKind::Any)), "modified");
+        assert_eq!(classify_event_kind(&EventKind::Any), "modified");
+    }
+
+    #[test]
+    fn path_is_ignored_nested_gitignore_can_re_include_what_root_excludes() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_ignore_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let sub = dir.join("sub");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+        std::fs::write(sub.join(".gitignore"), "!keep.log\n").unwrap();
+        std::fs::write(sub.join("keep.log"), "kept").unwrap();
+        std::fs::write(sub.join("drop.log"), "dropped").unwrap();
+
+        assert!(!path_is_ignored(&dir, &sub.join("keep.log"), false));
+        assert!(path_is_ignored(&dir, &sub.join("drop.log"), false));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn path_is_ignored_falls_back_to_git_info_exclude() {
+        let dir = std::env::temp_dir().join(format!("ft_fastpath_test_exclude_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(".git").join("info")).unwrap();
+        std::fs::write(dir.join(".git").join("info").join("exclude"), "*.tmp\n").unwrap();
+        std::fs::write(dir.join("scratch.tmp"), "x").unwrap();
+        std::fs::write(dir.join("keep.txt"), "x").unwrap();
+
+        assert!(path_is_ignored(&dir, &dir.join("scratch.tmp"), false));
+        assert!(!path_is_ignored(&dir, &dir.join("keep.txt"), false));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}